@@ -0,0 +1,125 @@
+use std::{env, fs, path::PathBuf};
+
+const DEFAULT_RCFILE: &str = ".rshrc";
+const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+/// User-configurable shell settings, loaded from `$RSH_RCFILE` (or
+/// `~/.rshrc`) at startup.
+pub struct Config {
+    pub prompt: String,
+    pub multiline_prompt: Option<String>,
+    pub history_limit: usize,
+    pub show_errors: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            prompt: "$ ".to_string(),
+            multiline_prompt: Some("> ".to_string()),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            show_errors: true,
+        }
+    }
+}
+
+impl Config {
+    /// Loads settings from the rc file on disk, if one exists, falling back
+    /// to defaults for any key that's missing, unrecognized, or invalid.
+    pub fn load() -> Self {
+        let mut config = Config::default();
+        let Some(path) = rcfile_path() else {
+            return config;
+        };
+        let Ok(contents) = fs::read_to_string(path) else {
+            return config;
+        };
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "prompt" => config.prompt = value.to_string(),
+                "multiline-prompt" => {
+                    config.multiline_prompt = if value.is_empty() {
+                        None
+                    } else {
+                        Some(value.to_string())
+                    };
+                }
+                "history-limit" => {
+                    if let Ok(limit) = value.parse() {
+                        config.history_limit = limit;
+                    }
+                }
+                "show-errors" => config.show_errors = value != "false",
+                _ => {}
+            }
+        }
+
+        config
+    }
+
+    /// Expands `\w` (current directory), `\u` (username), `\h` (hostname),
+    /// and `\$` escapes in a prompt template. Unrecognized escapes are left
+    /// as-is.
+    pub fn render(template: &str) -> String {
+        let mut out = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('w') => out.push_str(&current_dir_display()),
+                Some('u') => out.push_str(&env::var("USER").unwrap_or_default()),
+                Some('h') => out.push_str(&hostname()),
+                Some('$') => out.push('$'),
+                Some(other) => {
+                    out.push('\\');
+                    out.push(other);
+                }
+                None => out.push('\\'),
+            }
+        }
+
+        out
+    }
+}
+
+fn rcfile_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("RSH_RCFILE") {
+        return Some(PathBuf::from(path));
+    }
+    env::var("HOME").ok().map(|home| {
+        let mut path = PathBuf::from(home);
+        path.push(DEFAULT_RCFILE);
+        path
+    })
+}
+
+fn current_dir_display() -> String {
+    env::current_dir()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default()
+}
+
+fn hostname() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| {
+            fs::read_to_string("/proc/sys/kernel/hostname")
+                .ok()
+                .map(|s| s.trim().to_string())
+        })
+        .unwrap_or_default()
+}
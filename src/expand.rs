@@ -0,0 +1,83 @@
+use std::env;
+
+/// Expands `$NAME`, `${NAME}`, and `$?` occurrences in `token`.
+///
+/// Unset variables expand to an empty string; `$?` expands to `last_status`.
+/// A `$` not followed by a name, `{`, or `?` is passed through literally.
+pub fn expand(token: &str, last_status: i32) -> String {
+    let mut out = String::with_capacity(token.len());
+    let mut chars = token.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('?') => {
+                chars.next();
+                out.push_str(&last_status.to_string());
+            }
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                for nc in chars.by_ref() {
+                    if nc == '}' {
+                        break;
+                    }
+                    name.push(nc);
+                }
+                out.push_str(&env::var(&name).unwrap_or_default());
+            }
+            Some(&nc) if nc.is_alphabetic() || nc == '_' => {
+                let mut name = String::new();
+                while let Some(&nc) = chars.peek() {
+                    if nc.is_alphanumeric() || nc == '_' {
+                        name.push(nc);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                out.push_str(&env::var(&name).unwrap_or_default());
+            }
+            _ => out.push('$'),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_named_variable() {
+        env::set_var("RSH_TEST_EXPAND_VAR", "hello");
+        assert_eq!(expand("$RSH_TEST_EXPAND_VAR", 0), "hello");
+    }
+
+    #[test]
+    fn expands_braced_variable() {
+        env::set_var("RSH_TEST_EXPAND_VAR", "hello");
+        assert_eq!(expand("${RSH_TEST_EXPAND_VAR}!", 0), "hello!");
+    }
+
+    #[test]
+    fn unset_variable_expands_to_empty_string() {
+        env::remove_var("RSH_TEST_EXPAND_UNSET");
+        assert_eq!(expand("[$RSH_TEST_EXPAND_UNSET]", 0), "[]");
+    }
+
+    #[test]
+    fn expands_last_status() {
+        assert_eq!(expand("exit code $?", 7), "exit code 7");
+    }
+
+    #[test]
+    fn dollar_without_a_name_is_literal() {
+        assert_eq!(expand("cost: $5", 0), "cost: $5");
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+const DEFAULT_HISTFILE: &str = ".rsh_history";
+const DEFAULT_HISTORY_LIMIT: usize = 1000;
+
+/// In-memory command history, persisted to `$RSH_HISTFILE` (or
+/// `~/.rsh_history`) between sessions.
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    limit: usize,
+}
+
+impl History {
+    /// Loads history from the history file, if one exists.
+    pub fn load() -> Self {
+        let path = history_path();
+        let entries = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        History {
+            entries,
+            path,
+            limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    /// Appends a non-empty line, dropping the oldest entries once over the
+    /// `history-limit` cap.
+    pub fn add(&mut self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        self.entries.push(line.to_string());
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.entries.len() > self.limit {
+            let excess = self.entries.len() - self.limit;
+            self.entries.drain(0..excess);
+        }
+    }
+
+    /// Expands a line that is exactly `!!` (last command) or `!N` (command
+    /// number `N`, 1-indexed). Returns `None` if `line` isn't wholly such a
+    /// history reference, or refers to an entry that doesn't exist.
+    pub fn expand_bang(&self, line: &str) -> Option<String> {
+        if line == "!!" {
+            return self.entries.last().cloned();
+        }
+        let n: usize = line.strip_prefix('!')?.parse().ok()?;
+        self.entries.get(n.checked_sub(1)?).cloned()
+    }
+
+    /// Flushes history to the history file, if one is configured.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut file = fs::File::create(path)?;
+        for entry in &self.entries {
+            writeln!(file, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("RSH_HISTFILE") {
+        return Some(PathBuf::from(path));
+    }
+    env::var("HOME").ok().map(|home| {
+        let mut path = PathBuf::from(home);
+        path.push(DEFAULT_HISTFILE);
+        path
+    })
+}
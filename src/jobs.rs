@@ -0,0 +1,87 @@
+use std::process::{Child, ExitStatus};
+
+/// A single background job: every child process in its pipeline, kept around
+/// so `wait`/`fg` can block on them and `jobs` can report on them.
+pub struct Job {
+    pub id: usize,
+    pub pid: u32,
+    pub command: String,
+    children: Vec<Child>,
+}
+
+/// Tracks background (`&`) jobs started by the shell.
+#[derive(Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+    next_id: usize,
+}
+
+impl JobTable {
+    pub fn new() -> Self {
+        JobTable::default()
+    }
+
+    /// Registers a freshly spawned pipeline as a new background job and
+    /// returns its job id.
+    pub fn add(&mut self, children: Vec<Child>, command: String) -> usize {
+        self.next_id += 1;
+        let id = self.next_id;
+        let pid = children[0].id();
+        self.jobs.push(Job {
+            id,
+            pid,
+            command,
+            children,
+        });
+        id
+    }
+
+    pub fn list(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Non-blocking check for jobs whose every stage has exited. Completed
+    /// jobs are removed from the table and returned, with the final stage's
+    /// exit status, for the caller to print a completion notice.
+    pub fn reap_finished(&mut self) -> Vec<(usize, String, ExitStatus)> {
+        let mut done = Vec::new();
+        let mut still_running = Vec::new();
+
+        for mut job in self.jobs.drain(..) {
+            let all_exited = job
+                .children
+                .iter_mut()
+                .all(|child| matches!(child.try_wait(), Ok(Some(_))));
+            match job.children.last_mut().unwrap().try_wait() {
+                Ok(Some(status)) if all_exited => {
+                    done.push((job.id, job.command, status));
+                }
+                _ => still_running.push(job),
+            }
+        }
+
+        self.jobs = still_running;
+        done
+    }
+
+    /// Blocks until every tracked job has finished.
+    pub fn wait_all(&mut self) {
+        for mut job in self.jobs.drain(..) {
+            for child in &mut job.children {
+                let _ = child.wait();
+            }
+        }
+    }
+
+    /// Blocks until job `id` finishes, returning its final exit status.
+    pub fn wait_job(&mut self, id: usize) -> Option<ExitStatus> {
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        let mut job = self.jobs.remove(index);
+
+        let mut status = None;
+        for child in &mut job.children {
+            status = child.wait().ok();
+        }
+        status
+    }
+}
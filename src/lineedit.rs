@@ -0,0 +1,163 @@
+use std::io::{self, Read, Write};
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Termios {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 32],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+const TCSANOW: i32 = 0;
+const ICANON: u32 = 0o000002;
+const ECHO: u32 = 0o000010;
+const STDIN_FD: i32 = 0;
+
+extern "C" {
+    fn tcgetattr(fd: i32, termios: *mut Termios) -> i32;
+    fn tcsetattr(fd: i32, optional_actions: i32, termios: *const Termios) -> i32;
+}
+
+/// RAII guard that puts the terminal into raw mode (no line buffering, no
+/// local echo) and restores the original settings on drop.
+struct RawMode {
+    original: Termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let mut original: Termios = unsafe { std::mem::zeroed() };
+        if unsafe { tcgetattr(STDIN_FD, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut raw = original;
+        raw.c_lflag &= !(ICANON | ECHO);
+        if unsafe { tcsetattr(STDIN_FD, TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            tcsetattr(STDIN_FD, TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Reads one line from stdin with Up/Down arrow recall against `history`,
+/// redrawing the line as it's edited. Returns `None` at EOF (Ctrl-D on an
+/// empty line, or stdin closed).
+///
+/// Falls back to a plain `read_line` without history recall when raw mode can't be
+/// enabled, e.g. because stdin isn't a terminal.
+pub fn read_line(prompt: &str, history: &[String]) -> io::Result<Option<String>> {
+    match RawMode::enable() {
+        Ok(raw) => {
+            let result = read_line_raw(prompt, history);
+            drop(raw);
+            result
+        }
+        Err(_) => read_line_plain(prompt),
+    }
+}
+
+fn read_line_raw(prompt: &str, history: &[String]) -> io::Result<Option<String>> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut stderr = io::stderr();
+
+    let mut buf = String::new();
+    let mut history_index = history.len();
+    let mut byte = [0u8; 1];
+    // Bytes of a multi-byte UTF-8 sequence seen so far but not yet decodable
+    // into a char, since stdin is read one raw byte at a time.
+    let mut utf8_pending: Vec<u8> = Vec::new();
+
+    redraw(&mut stderr, prompt, &buf)?;
+
+    loop {
+        if input.read(&mut byte)? == 0 {
+            return if buf.is_empty() { Ok(None) } else { Ok(Some(buf)) };
+        }
+
+        match byte[0] {
+            b'\r' | b'\n' => {
+                stderr.write_all(b"\r\n")?;
+                return Ok(Some(buf));
+            }
+            0x04 if buf.is_empty() => return Ok(None), // Ctrl-D
+            0x7f | 0x08 => {
+                buf.pop();
+                redraw(&mut stderr, prompt, &buf)?;
+            }
+            0x1b => {
+                let mut seq = [0u8; 2];
+                if input.read(&mut seq)? < 2 || seq[0] != b'[' {
+                    continue;
+                }
+                match seq[1] {
+                    b'A' if history_index > 0 => {
+                        history_index -= 1;
+                        buf = history[history_index].clone();
+                        redraw(&mut stderr, prompt, &buf)?;
+                    }
+                    b'B' if history_index < history.len() => {
+                        history_index += 1;
+                        buf = history.get(history_index).cloned().unwrap_or_default();
+                        redraw(&mut stderr, prompt, &buf)?;
+                    }
+                    _ => {}
+                }
+            }
+            c if c < 0x80 => {
+                buf.push(c as char);
+                redraw(&mut stderr, prompt, &buf)?;
+            }
+            c => {
+                utf8_pending.push(c);
+                match std::str::from_utf8(&utf8_pending) {
+                    Ok(s) => {
+                        buf.push_str(s);
+                        utf8_pending.clear();
+                        redraw(&mut stderr, prompt, &buf)?;
+                    }
+                    Err(e) if e.error_len().is_none() => {
+                        // A valid prefix of a multi-byte sequence; wait for
+                        // the rest before decoding and redrawing.
+                    }
+                    Err(_) => {
+                        // Not valid UTF-8 even with more bytes; drop it
+                        // rather than getting stuck waiting forever.
+                        utf8_pending.clear();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Clears the current line and rewrites `prompt` + `buf`.
+fn redraw(stderr: &mut io::Stderr, prompt: &str, buf: &str) -> io::Result<()> {
+    write!(stderr, "\r\x1b[K{}{}", prompt, buf)?;
+    stderr.flush()
+}
+
+fn read_line_plain(prompt: &str) -> io::Result<Option<String>> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+}
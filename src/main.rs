@@ -1,8 +1,18 @@
+mod config;
+mod expand;
+mod history;
+mod jobs;
+mod lineedit;
+
+use config::Config;
+use history::History;
+use jobs::JobTable;
 use std::{
     env,
-    io::{self, Write},
+    fs::{self, OpenOptions},
+    io,
     path::Path,
-    process::Command,
+    process::{Child, Command, ExitCode, ExitStatus, Stdio},
 };
 
 const MAX_CMD_LENGTH: usize = 1000;
@@ -11,139 +21,789 @@ const MAX_ARGS: usize = 100;
 #[derive(Debug)]
 enum ShellError {
     MismatchedQuotes,
+    NeedsMoreInput,
+    Exit,
     TooManyArgs,
     CommandLineTooLong,
     CommandFailed(()),
     CdFailed,
     IoError(io::Error),
+    PipelineFailed(ExitStatus),
+    EmptyCommand,
+    MissingRedirectTarget,
+}
+
+#[derive(Debug)]
+enum QuoteState {
+    Unquoted,
+    SingleQuoted,
+    DoubleQuoted,
+    Escaped(Box<QuoteState>),
 }
 
-fn parse_command(input: &str) -> Result<Vec<String>, ShellError> {
+#[derive(Debug, Default)]
+struct Stage {
+    args: Vec<String>,
+    stdin_file: Option<String>,
+    stdout_file: Option<String>,
+    append: bool,
+}
+
+/// A parsed command line: the pipeline stages to run, and whether a trailing
+/// `&` asked for it to run in the background.
+#[derive(Debug, Default)]
+struct Pipeline {
+    stages: Vec<Stage>,
+    background: bool,
+}
+
+/// A raw token broken into maximal runs of same-quotedness text, e.g. the
+/// word `'$FOO'yy` tokenizes to the parts `[("$FOO", true), ("yy", false)]`.
+/// Expansion is applied per part rather than once for the whole token, so a
+/// single-quoted fragment stays literal even when immediately followed by
+/// unquoted or double-quoted text in the same word.
+#[derive(Debug)]
+struct Token {
+    parts: Vec<(String, bool)>,
+}
+
+/// Splits a tokenized line on `;` into one token group per sequential
+/// command, *before* any expansion happens. Expansion has to wait until a
+/// group is actually about to run, since an earlier group in the same line
+/// (e.g. `export FOO=bar`) can change what a later group's `$FOO` or `$?`
+/// expands to.
+fn tokenize_groups(input: &str) -> Result<Vec<Vec<Token>>, ShellError> {
     if input.len() > MAX_CMD_LENGTH {
         return Err(ShellError::CommandLineTooLong);
     }
 
+    let mut groups = vec![Vec::new()];
+    for token in tokenize(input)? {
+        if matches!(token.parts.as_slice(), [(text, true)] if text == ";") {
+            groups.push(Vec::new());
+        } else {
+            groups.last_mut().unwrap().push(token);
+        }
+    }
+    Ok(groups)
+}
+
+/// Expands every token in a single group against `last_status`, which is the
+/// live exit status at the point this group is about to run (not the status
+/// from before the whole line started).
+fn expand_tokens(tokens: Vec<Token>, last_status: i32) -> Result<Vec<String>, ShellError> {
+    let tokens: Vec<String> = tokens
+        .into_iter()
+        .map(|t| {
+            t.parts
+                .into_iter()
+                .map(|(text, literal)| {
+                    if literal {
+                        text
+                    } else {
+                        expand::expand(&text, last_status)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    if tokens.len() > MAX_ARGS {
+        return Err(ShellError::TooManyArgs);
+    }
+
+    Ok(tokens)
+}
+
+fn operator_token(text: &str) -> Token {
+    Token {
+        parts: vec![(text.to_string(), true)],
+    }
+}
+
+#[allow(unused_assignments)]
+fn tokenize(input: &str) -> Result<Vec<Token>, ShellError> {
     let mut tokens = Vec::new();
-    let mut current_token = String::new();
-    let mut in_single_quotes = false;
-    let mut in_double_quotes = false;
+    let mut parts: Vec<(String, bool)> = Vec::new();
+    let mut run = String::new();
+    let mut run_literal = true;
+    let mut state = QuoteState::Unquoted;
     let mut chars = input.chars().peekable();
 
+    // Pushes `c` into the current run, starting a new run first if `c`'s
+    // quotedness differs from the run in progress, so each run stays
+    // uniformly literal or expandable.
+    macro_rules! push_char {
+        ($c:expr, $literal:expr) => {{
+            if run_literal != $literal && !run.is_empty() {
+                parts.push((std::mem::take(&mut run), run_literal));
+            }
+            run_literal = $literal;
+            run.push($c);
+        }};
+    }
+
+    macro_rules! flush {
+        () => {{
+            if !run.is_empty() {
+                parts.push((std::mem::take(&mut run), run_literal));
+            }
+            if !parts.is_empty() {
+                tokens.push(Token {
+                    parts: std::mem::take(&mut parts),
+                });
+            }
+            run_literal = true;
+        }};
+    }
+
     while let Some(c) = chars.next() {
-        match c {
-            '\'' if !in_double_quotes => in_single_quotes = !in_single_quotes,
-            '"' if !in_single_quotes => in_double_quotes = !in_double_quotes,
-            ' ' if !in_single_quotes && !in_double_quotes => {
-                if !current_token.is_empty() {
-                    tokens.push(current_token);
-                    current_token = String::new();
+        state = match state {
+            QuoteState::Escaped(prev) => match *prev {
+                QuoteState::DoubleQuoted => {
+                    if matches!(c, '"' | '\\' | '$' | '\n') {
+                        // An escaped special char stays literal text, not
+                        // subject to re-expansion (e.g. `\$` must not start
+                        // a variable reference).
+                        push_char!(c, true);
+                    } else {
+                        push_char!('\\', false);
+                        push_char!(c, false);
+                    }
+                    QuoteState::DoubleQuoted
                 }
-            }
-            _ => current_token.push(c),
+                other => {
+                    // Outside quotes, an escaped char (e.g. `\$`, `\ `) is
+                    // always protected from expansion.
+                    push_char!(c, true);
+                    other
+                }
+            },
+            QuoteState::Unquoted => match c {
+                '\\' => QuoteState::Escaped(Box::new(QuoteState::Unquoted)),
+                '\'' => QuoteState::SingleQuoted,
+                '"' => QuoteState::DoubleQuoted,
+                ' ' => {
+                    flush!();
+                    QuoteState::Unquoted
+                }
+                '|' => {
+                    flush!();
+                    tokens.push(operator_token("|"));
+                    QuoteState::Unquoted
+                }
+                '>' => {
+                    flush!();
+                    if chars.peek() == Some(&'>') {
+                        chars.next();
+                        tokens.push(operator_token(">>"));
+                    } else {
+                        tokens.push(operator_token(">"));
+                    }
+                    QuoteState::Unquoted
+                }
+                '<' => {
+                    flush!();
+                    tokens.push(operator_token("<"));
+                    QuoteState::Unquoted
+                }
+                '&' => {
+                    flush!();
+                    tokens.push(operator_token("&"));
+                    QuoteState::Unquoted
+                }
+                ';' => {
+                    flush!();
+                    tokens.push(operator_token(";"));
+                    QuoteState::Unquoted
+                }
+                _ => {
+                    push_char!(c, false);
+                    QuoteState::Unquoted
+                }
+            },
+            QuoteState::SingleQuoted => match c {
+                '\'' => QuoteState::Unquoted,
+                _ => {
+                    push_char!(c, true);
+                    QuoteState::SingleQuoted
+                }
+            },
+            QuoteState::DoubleQuoted => match c {
+                '"' => QuoteState::Unquoted,
+                '\\' => QuoteState::Escaped(Box::new(QuoteState::DoubleQuoted)),
+                _ => {
+                    push_char!(c, false);
+                    QuoteState::DoubleQuoted
+                }
+            },
+        };
+    }
+
+    match state {
+        QuoteState::SingleQuoted | QuoteState::DoubleQuoted => Err(ShellError::MismatchedQuotes),
+        QuoteState::Escaped(_) => Err(ShellError::NeedsMoreInput),
+        QuoteState::Unquoted => {
+            flush!();
+            Ok(tokens)
         }
     }
+}
 
-    if in_single_quotes || in_double_quotes {
-        return Err(ShellError::MismatchedQuotes);
+fn build_stages(mut tokens: Vec<String>) -> Result<Pipeline, ShellError> {
+    let background = tokens.last().map(String::as_str) == Some("&");
+    if background {
+        tokens.pop();
     }
 
-    if !current_token.is_empty() {
-        tokens.push(current_token);
+    let mut stages = Vec::new();
+    let mut stage = Stage::default();
+    let mut tokens = tokens.into_iter();
+
+    while let Some(tok) = tokens.next() {
+        match tok.as_str() {
+            "|" => {
+                stages.push(std::mem::take(&mut stage));
+            }
+            "<" => {
+                stage.stdin_file = Some(tokens.next().ok_or(ShellError::MissingRedirectTarget)?);
+            }
+            ">" => {
+                stage.stdout_file = Some(tokens.next().ok_or(ShellError::MissingRedirectTarget)?);
+                stage.append = false;
+            }
+            ">>" => {
+                stage.stdout_file = Some(tokens.next().ok_or(ShellError::MissingRedirectTarget)?);
+                stage.append = true;
+            }
+            _ => stage.args.push(tok),
+        }
     }
+    stages.push(stage);
 
-    if tokens.len() > MAX_ARGS {
-        return Err(ShellError::TooManyArgs);
+    // A blank input line parses to a single empty stage, which is a no-op
+    // handled by `execute_command`. But an empty stage inside a multi-stage
+    // pipeline (`ls |`, `| ls`, `ls || true`) is malformed and must be
+    // rejected here, before `spawn_stages` indexes into `stage.args[0]`.
+    if stages.len() > 1 && stages.iter().any(|s| s.args.is_empty()) {
+        return Err(ShellError::EmptyCommand);
     }
 
-    Ok(tokens)
+    Ok(Pipeline { stages, background })
 }
 
-fn execute_command(args: Vec<String>) -> Result<(), ShellError> {
-    if args.is_empty() {
+/// Reconstructs a display string for a pipeline (e.g. `sleep 1 &`), used for
+/// `jobs`/`fg` listings. This is built from the parsed `Pipeline` rather than
+/// sliced out of the raw input line, since the pipeline is only the part of
+/// a `;`-separated line that's actually being backgrounded.
+fn describe_pipeline(pipeline: &Pipeline) -> String {
+    let mut words = Vec::new();
+    for (i, stage) in pipeline.stages.iter().enumerate() {
+        if i > 0 {
+            words.push("|".to_string());
+        }
+        words.extend(stage.args.iter().cloned());
+        if let Some(path) = &stage.stdin_file {
+            words.push("<".to_string());
+            words.push(path.clone());
+        }
+        if let Some(path) = &stage.stdout_file {
+            words.push(if stage.append { ">>" } else { ">" }.to_string());
+            words.push(path.clone());
+        }
+    }
+    if pipeline.background {
+        words.push("&".to_string());
+    }
+    words.join(" ")
+}
+
+fn execute_command(
+    pipeline: Pipeline,
+    history: &History,
+    jobs: &mut JobTable,
+    config: &Config,
+) -> Result<(), ShellError> {
+    let command_text = describe_pipeline(&pipeline);
+    let Pipeline { stages, background } = pipeline;
+
+    if stages.len() == 1 && stages[0].args.is_empty() {
         return Ok(());
     }
 
-    match args[0].as_str() {
-        "exit" => std::process::exit(0),
-        "cd" => {
-            if args.len() != 2 {
-                eprintln!("error: cd requires exactly one argument");
-                return Err(ShellError::CdFailed);
+    if stages.len() == 1 {
+        let args = &stages[0].args;
+        match args[0].as_str() {
+            "exit" => return Err(ShellError::Exit),
+            "history" => {
+                for (i, entry) in history.entries().iter().enumerate() {
+                    println!("{:5}  {}", i + 1, entry);
+                }
+                return Ok(());
             }
-            if env::set_current_dir(Path::new(&args[1])).is_err() {
-                return Err(ShellError::CdFailed);
+            "jobs" => {
+                for job in jobs.list() {
+                    println!("[{}]  {}  {}", job.id, job.pid, job.command);
+                }
+                return Ok(());
             }
-            Ok(())
-        }
-        cmd => {
-            let mut command = if cmd.contains('/') {
-                Command::new(cmd)
-            } else {
-                Command::new(cmd)
-            };
-
-            command.args(&args[1..]);
-
-            match command.status() {
-                Ok(status) => {
-                    if !status.success() {
-                        let code = status.code().unwrap_or(1);
-                        eprintln!("error: command exited with code {}", code);
+            "wait" => {
+                jobs.wait_all();
+                return Ok(());
+            }
+            "fg" => {
+                if args.len() != 2 {
+                    if config.show_errors {
+                        eprintln!("error: fg requires a job number");
+                    }
+                    return Err(ShellError::CommandFailed(()));
+                }
+                let id: usize = match args[1].parse() {
+                    Ok(id) => id,
+                    Err(_) => {
+                        if config.show_errors {
+                            eprintln!("error: fg: invalid job number");
+                        }
+                        return Err(ShellError::CommandFailed(()));
+                    }
+                };
+                return match jobs.wait_job(id) {
+                    Some(status) if !status.success() => Err(ShellError::PipelineFailed(status)),
+                    Some(_) => Ok(()),
+                    None => {
+                        if config.show_errors {
+                            eprintln!("error: fg: no such job");
+                        }
                         Err(ShellError::CommandFailed(()))
-                    } else {
-                        Ok(())
                     }
+                };
+            }
+            "cd" => {
+                if args.len() != 2 {
+                    if config.show_errors {
+                        eprintln!("error: cd requires exactly one argument");
+                    }
+                    return Err(ShellError::CdFailed);
+                }
+                return if env::set_current_dir(Path::new(&args[1])).is_err() {
+                    Err(ShellError::CdFailed)
+                } else {
+                    Ok(())
+                };
+            }
+            "export" => {
+                if args.len() != 2 {
+                    if config.show_errors {
+                        eprintln!("error: export requires a NAME=value argument");
+                    }
+                    return Err(ShellError::CommandFailed(()));
                 }
-                Err(e) => {
-                    eprintln!("error: failed to execute command: {}", e);
-                    Err(ShellError::IoError(e))
+                return match args[1].split_once('=') {
+                    Some((name, value)) => {
+                        env::set_var(name, value);
+                        Ok(())
+                    }
+                    None => {
+                        if config.show_errors {
+                            eprintln!("error: export requires a NAME=value argument");
+                        }
+                        Err(ShellError::CommandFailed(()))
+                    }
+                };
+            }
+            "unset" => {
+                if args.len() != 2 {
+                    if config.show_errors {
+                        eprintln!("error: unset requires exactly one argument");
+                    }
+                    return Err(ShellError::CommandFailed(()));
                 }
+                env::remove_var(&args[1]);
+                return Ok(());
             }
+            _ => {}
         }
     }
+
+    let children = spawn_stages(stages)?;
+
+    if background {
+        let id = jobs.add(children, command_text);
+        println!("[{}] {}", id, jobs.list().last().unwrap().pid);
+        return Ok(());
+    }
+
+    let mut last_status = None;
+    for mut child in children {
+        last_status = Some(child.wait().map_err(ShellError::IoError)?);
+    }
+
+    match last_status {
+        Some(status) if !status.success() => Err(ShellError::PipelineFailed(status)),
+        _ => Ok(()),
+    }
 }
 
-fn main() -> io::Result<()> {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut input = String::new();
+/// Spawns every stage of the pipeline, wiring each stage's stdout into the
+/// next stage's stdin (or to/from a file when a redirection was given).
+/// Spawning happens for every stage before anything is waited on. If a later
+/// stage fails to open its redirection or to spawn, every child already
+/// spawned is killed and reaped before the error is returned, so a failed
+/// pipeline never leaves earlier stages running as orphans/zombies.
+fn spawn_stages(stages: Vec<Stage>) -> Result<Vec<Child>, ShellError> {
+    let count = stages.len();
+    let mut children: Vec<Child> = Vec::with_capacity(count);
+    let mut previous_stdout = None;
 
-    loop {
-        eprint!("$ ");
-        let _ = io::stderr().flush();
+    for (i, stage) in stages.into_iter().enumerate() {
+        let mut command = Command::new(&stage.args[0]);
+        command.args(&stage.args[1..]);
+
+        if let Some(path) = &stage.stdin_file {
+            match OpenOptions::new().read(true).open(path) {
+                Ok(file) => command.stdin(Stdio::from(file)),
+                Err(e) => return kill_spawned(children, e),
+            };
+        } else if let Some(stdout) = previous_stdout.take() {
+            command.stdin(stdout);
+        }
+
+        if let Some(path) = &stage.stdout_file {
+            match OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(stage.append)
+                .truncate(!stage.append)
+                .open(path)
+            {
+                Ok(file) => command.stdout(Stdio::from(file)),
+                Err(e) => return kill_spawned(children, e),
+            };
+        } else if i + 1 < count {
+            command.stdout(Stdio::piped());
+        }
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => return kill_spawned(children, e),
+        };
+        previous_stdout = child.stdout.take().map(Stdio::from);
+        children.push(child);
+    }
+
+    Ok(children)
+}
+
+/// Kills and reaps every already-spawned stage before propagating `err` as
+/// the pipeline's `IoError`, used when a later stage fails to set up.
+fn kill_spawned(children: Vec<Child>, err: io::Error) -> Result<Vec<Child>, ShellError> {
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    Err(ShellError::IoError(err))
+}
+
+/// Reads a line, transparently joining continuation lines requested by a
+/// trailing backslash, until a complete logical command line is available
+/// (or EOF is hit). Returns `None` at EOF with nothing pending.
+fn read_logical_line(history: &History, config: &Config) -> io::Result<Option<String>> {
+    let prompt = Config::render(&config.prompt);
+    let mut line = match lineedit::read_line(&prompt, history.entries())? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+
+    while let Err(ShellError::NeedsMoreInput) = tokenize(&line) {
+        line.pop();
+        let cont_prompt = config.multiline_prompt.as_deref().unwrap_or("");
+        match lineedit::read_line(&Config::render(cont_prompt), history.entries())? {
+            Some(cont) => line.push_str(&cont),
+            None => break,
+        }
+    }
+
+    Ok(Some(line))
+}
+
+fn print_exec_error(e: &ShellError, config: &Config) {
+    if !config.show_errors {
+        return;
+    }
+    match e {
+        ShellError::CdFailed => eprintln!("error: cd failed"),
+        ShellError::CommandFailed(_) => (), // Already printed
+        ShellError::IoError(e) => eprintln!("error: {}", e),
+        ShellError::PipelineFailed(status) => eprintln!("error: pipeline exited with {}", status),
+        _ => eprintln!("error: {:?}", e),
+    }
+}
+
+fn print_parse_error(e: &ShellError, config: &Config) {
+    if !config.show_errors {
+        return;
+    }
+    match e {
+        ShellError::MismatchedQuotes => eprintln!("error: mismatched quotes"),
+        ShellError::TooManyArgs => eprintln!("error: too many arguments"),
+        ShellError::CommandLineTooLong => eprintln!("error: command line too long"),
+        ShellError::EmptyCommand => eprintln!("error: empty command in pipeline"),
+        ShellError::MissingRedirectTarget => eprintln!("error: missing redirection target"),
+        _ => eprintln!("error: {:?}", e),
+    }
+}
+
+/// Runs every `;`-separated pipeline in one logical line in sequence,
+/// expanding each group only once its turn to run, so a later group sees
+/// the exit status and environment left behind by an earlier one. Returns
+/// `Ok(status)` with the last command's exit status, or `Err(status)` if the
+/// line asked to exit the shell.
+fn run_line(
+    input: &str,
+    last_status: i32,
+    history: &History,
+    jobs: &mut JobTable,
+    config: &Config,
+) -> Result<i32, i32> {
+    let groups = match tokenize_groups(input) {
+        Ok(groups) => groups,
+        Err(e) => {
+            print_parse_error(&e, config);
+            return Ok(1);
+        }
+    };
+
+    let mut status = last_status;
+    for group in groups {
+        let pipeline = match expand_tokens(group, status).and_then(build_stages) {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                print_parse_error(&e, config);
+                status = 1;
+                continue;
+            }
+        };
+
+        match execute_command(pipeline, history, jobs, config) {
+            Ok(()) => status = 0,
+            Err(ShellError::Exit) => return Err(status),
+            Err(e) => {
+                status = match &e {
+                    ShellError::PipelineFailed(s) => s.code().unwrap_or(1),
+                    _ => 1,
+                };
+                print_exec_error(&e, config);
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Runs each line from `lines` in order, skipping blank lines and (when
+/// `skip_comments` is set) lines starting with `#`. A trailing backslash
+/// joins the next line onto the current one, the same continuation rule
+/// `read_logical_line` applies in interactive mode, so a script or `-c`
+/// string can span a logical command across multiple lines too. Stops
+/// early if a line requests `exit`. Returns the final exit status.
+fn run_lines<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    history: &mut History,
+    jobs: &mut JobTable,
+    config: &Config,
+    skip_comments: bool,
+) -> i32 {
+    let mut last_status = 0;
+    let mut lines = lines;
+    while let Some(raw) = lines.next() {
+        if raw.trim().is_empty() || (skip_comments && raw.trim_start().starts_with('#')) {
+            continue;
+        }
+
+        let mut line = raw.to_string();
+        while let Err(ShellError::NeedsMoreInput) = tokenize(&line) {
+            line.pop();
+            match lines.next() {
+                Some(cont) => line.push_str(cont),
+                None => break,
+            }
+        }
+        let input = line.trim();
+
+        match run_line(input, last_status, history, jobs, config) {
+            Ok(status) => last_status = status,
+            Err(status) => return status,
+        }
+    }
+    last_status
+}
 
-        input.clear();
-        if stdin.read_line(&mut input)? == 0 {
-            // EOF received
-            break;
+fn run_interactive(history: &mut History, jobs: &mut JobTable, config: &Config) -> io::Result<i32> {
+    let mut last_status: i32 = 0;
+
+    loop {
+        for (id, command, status) in jobs.reap_finished() {
+            println!("[{}]+  Done({})  {}", id, status.code().unwrap_or(0), command);
         }
 
-        let input = input.trim();
+        let line = match read_logical_line(history, config)? {
+            Some(line) => line,
+            None => break, // EOF received
+        };
+
+        let input = line.trim();
         if input.is_empty() {
             continue;
         }
 
-        match parse_command(input) {
-            Ok(args) => {
-                if let Err(e) = execute_command(args) {
-                    match e {
-                        ShellError::CdFailed => eprintln!("error: cd failed"),
-                        ShellError::CommandFailed(_) => (), // Already printed
-                        ShellError::IoError(e) => eprintln!("error: {}", e),
-                        _ => eprintln!("error: {:?}", e),
-                    }
-                }
+        let input = history.expand_bang(input).unwrap_or_else(|| input.to_string());
+        history.add(&input);
+
+        match run_line(&input, last_status, history, jobs, config) {
+            Ok(status) => last_status = status,
+            Err(status) => {
+                last_status = status;
+                break;
             }
+        }
+    }
+
+    Ok(last_status)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let config = Config::load();
+    let mut history = History::load();
+    history.set_limit(config.history_limit);
+    let mut jobs = JobTable::new();
+
+    let status = if let Some(pos) = args.iter().position(|a| a == "-c") {
+        match args.get(pos + 1) {
+            Some(command) => run_lines(command.lines(), &mut history, &mut jobs, &config, false),
+            None => {
+                eprintln!("error: -c requires a command string");
+                1
+            }
+        }
+    } else if let Some(path) = args.get(1) {
+        match fs::read_to_string(path) {
+            Ok(contents) => run_lines(contents.lines(), &mut history, &mut jobs, &config, true),
             Err(e) => {
-                match e {
-                    ShellError::MismatchedQuotes => eprintln!("error: mismatched quotes"),
-                    ShellError::TooManyArgs => eprintln!("error: too many arguments"),
-                    ShellError::CommandLineTooLong => eprintln!("error: command line too long"),
-                    _ => eprintln!("error: {:?}", e),
-                }
+                eprintln!("error: {}: {}", path, e);
+                1
+            }
+        }
+    } else {
+        match run_interactive(&mut history, &mut jobs, &config) {
+            Ok(status) => status,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                1
             }
         }
+    };
+
+    let _ = history.save();
+    ExitCode::from(status.rem_euclid(256) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tokenizes and expands `input` against a fixed exit status, flattening
+    /// the result to plain words the way `run_line` would before handing
+    /// them to `build_stages`.
+    fn words(input: &str) -> Vec<String> {
+        expand_tokens(tokenize(input).unwrap(), 0).unwrap()
     }
 
-    Ok(())
-} 
\ No newline at end of file
+    #[test]
+    fn tokenize_splits_on_whitespace() {
+        assert_eq!(words("echo  foo bar"), vec!["echo", "foo", "bar"]);
+    }
+
+    #[test]
+    fn tokenize_keeps_single_quoted_text_literal() {
+        assert_eq!(words("echo 'a b' c"), vec!["echo", "a b", "c"]);
+    }
+
+    #[test]
+    fn tokenize_backslash_escapes_a_space_outside_quotes() {
+        assert_eq!(words(r"echo foo\ bar"), vec!["echo", "foo bar"]);
+    }
+
+    #[test]
+    fn tokenize_double_quotes_escape_dollar_to_prevent_expansion() {
+        assert_eq!(words(r#"echo "a\$b""#), vec!["echo", "a$b"]);
+    }
+
+    #[test]
+    fn tokenize_rejects_unterminated_quote() {
+        assert!(matches!(
+            tokenize("echo 'unterminated"),
+            Err(ShellError::MismatchedQuotes)
+        ));
+    }
+
+    #[test]
+    fn tokenize_reports_dangling_backslash_as_needs_more_input() {
+        assert!(matches!(
+            tokenize("echo foo\\"),
+            Err(ShellError::NeedsMoreInput)
+        ));
+    }
+
+    #[test]
+    fn tokenize_groups_splits_on_semicolon() {
+        let groups = tokenize_groups("echo a; echo b").unwrap();
+        assert_eq!(groups.len(), 2);
+    }
+
+    #[test]
+    fn tokenize_groups_rejects_overlong_input() {
+        let long = "a".repeat(MAX_CMD_LENGTH + 1);
+        assert!(matches!(
+            tokenize_groups(&long),
+            Err(ShellError::CommandLineTooLong)
+        ));
+    }
+
+    #[test]
+    fn build_stages_parses_a_pipeline_with_redirections() {
+        let pipeline = build_stages(words("cat < in.txt | sort > out.txt")).unwrap();
+        assert_eq!(pipeline.stages.len(), 2);
+        assert_eq!(pipeline.stages[0].args, vec!["cat".to_string()]);
+        assert_eq!(pipeline.stages[0].stdin_file.as_deref(), Some("in.txt"));
+        assert_eq!(pipeline.stages[1].args, vec!["sort".to_string()]);
+        assert_eq!(pipeline.stages[1].stdout_file.as_deref(), Some("out.txt"));
+        assert!(!pipeline.stages[1].append);
+    }
+
+    #[test]
+    fn build_stages_rejects_an_empty_stage_in_a_pipeline() {
+        assert!(matches!(
+            build_stages(words("ls |")),
+            Err(ShellError::EmptyCommand)
+        ));
+    }
+
+    #[test]
+    fn build_stages_rejects_a_redirection_with_no_target() {
+        assert!(matches!(
+            build_stages(words("cat <")),
+            Err(ShellError::MissingRedirectTarget)
+        ));
+    }
+
+    #[test]
+    fn build_stages_detects_a_trailing_background_marker() {
+        let pipeline = build_stages(words("sleep 1 &")).unwrap();
+        assert!(pipeline.background);
+        assert_eq!(
+            pipeline.stages[0].args,
+            vec!["sleep".to_string(), "1".to_string()]
+        );
+    }
+}